@@ -4,6 +4,14 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::TempDir;
 
+/// pve-manager releases this generator has been validated against.
+const SUPPORTED_PVE_VERSIONS: &[&str] = &["8.0", "8.1", "8.2", "8.3", "8.4"];
+
+/// Marker used to detect an already-patched pvemanagerlib.js.
+const MOOSEFS_MARKER: &str = "PVE.storage.MooseFSInputPanel";
+
+const INSTALLED_JS: &str = "/usr/share/pve-manager/js/pvemanagerlib.js";
+
 const MOOSEFS_UI_PANEL: &str = r#"Ext.define('PVE.storage.MooseFSController', {
     extend: 'Ext.app.ViewController',
     alias: 'controller.pve-storage-moosefs'
@@ -110,6 +118,35 @@ Ext.define('PVE.storage.MooseFSInputPanel', {
     },
 });"#;
 
+/// Textual anchors in pvemanagerlib.js that the MooseFS blocks are inserted
+/// before. Proxmox occasionally reorders or renames storage panels between
+/// releases, so these are resolved per version rather than hard-coded.
+struct AnchorSet {
+    /// Storage-type entry the MooseFS type is inserted before.
+    storage_type_anchor: &'static str,
+    /// `Ext.define` of the panel the MooseFS panel is inserted before.
+    panel_anchor: &'static str,
+}
+
+/// One entry of the supported-version compatibility table, covering an
+/// inclusive `(major, minor)` range of pve-manager releases.
+struct VersionAnchors {
+    min: (u32, u32),
+    max: (u32, u32),
+    anchors: AnchorSet,
+}
+
+/// Anchor sets for every supported pve-manager release range. Kept sorted by
+/// version so the highest known-good version is easy to report.
+const ANCHOR_TABLE: &[VersionAnchors] = &[VersionAnchors {
+    min: (8, 0),
+    max: (8, 4),
+    anchors: AnchorSet {
+        storage_type_anchor: "cephfs: {",
+        panel_anchor: "Ext.define('PVE.storage.BTRFSInputPanel'",
+    },
+}];
+
 const STORAGE_TYPES_ADDITION: &str = r#"            moosefs: {
                 name: 'MooseFS',
                 ipanel: 'MooseFSInputPanel',
@@ -118,32 +155,443 @@ const STORAGE_TYPES_ADDITION: &str = r#"            moosefs: {
             },"#;
 
 fn main() -> Result<()> {
-    println!("MooseFS Patch Generator for Proxmox VE");
+    let mode = std::env::args().nth(1);
+
+    match mode.as_deref() {
+        Some("doctor") => doctor(),
+        Some("repo") => ensure_moosefs_repo(),
+        Some("uninstall") => uninstall(),
+        Some("info") => info(std::env::args().any(|a| a == "--json")),
+        _ => generate(),
+    }
+}
+
+/// Base URI (host + path, without the trailing suite) of the MooseFS APT repo.
+const MOOSEFS_REPO_BASE: &str = "https://ppa.moosefs.com/moosefs-3/apt/debian";
+
+/// Debian base codenames the MooseFS repo is known to publish for.
+const KNOWN_CODENAMES: &[&str] = &["bookworm", "trixie"];
+
+const SOURCES_LIST_D: &str = "/etc/apt/sources.list.d";
+
+/// A single parsed `deb`/`deb-src` line from a sources list file.
+struct SourceEntry {
+    enabled: bool,
+    is_src: bool,
+    uri: String,
+    /// The suite(s) the entry targets; whitespace-separated for deb822 stanzas
+    /// that list more than one.
+    suite: String,
+}
+
+/// The parsed contents of one file under `sources.list.d`.
+struct SourceFile {
+    path: PathBuf,
+    entries: Vec<SourceEntry>,
+}
+
+/// Parse a one-line `deb`/`deb-src` entry, tolerating a leading `#` that
+/// disables it. Returns `None` for blank lines and unrelated comments.
+fn parse_source_line(line: &str) -> Option<SourceEntry> {
+    let trimmed = line.trim();
+    let (enabled, rest) = match trimmed.strip_prefix('#') {
+        Some(rest) => (false, rest.trim_start()),
+        None => (true, trimmed),
+    };
+
+    let mut tokens = rest.split_whitespace();
+    let kind = tokens.next()?;
+    let is_src = match kind {
+        "deb" => false,
+        "deb-src" => true,
+        _ => return None,
+    };
+
+    // Skip an optional `[arch=... signed-by=...]` option group, which may span
+    // several whitespace-separated tokens.
+    let mut uri = tokens.next()?;
+    if uri.starts_with('[') {
+        while !uri.ends_with(']') {
+            uri = tokens.next()?;
+        }
+        uri = tokens.next()?;
+    }
+
+    let suite = tokens.next()?.to_string();
+
+    Some(SourceEntry {
+        enabled,
+        is_src,
+        uri: uri.to_string(),
+        suite,
+    })
+}
+
+/// Parse every `*.list` and `*.sources` file under `sources.list.d` into a model.
+fn parse_sources_list_d(dir: &Path) -> Vec<SourceFile> {
+    let mut files = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return files,
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let parsed = match path.extension().and_then(|e| e.to_str()) {
+            // One-line `deb`/`deb-src` format.
+            Some("list") => fs::read_to_string(&path)
+                .map(|content| content.lines().filter_map(parse_source_line).collect()),
+            // deb822 stanza format (modern PVE / trixie ship these).
+            Some("sources") => fs::read_to_string(&path).map(|content| parse_deb822(&content)),
+            _ => continue,
+        };
+        if let Ok(parsed) = parsed {
+            files.push(SourceFile { path, entries: parsed });
+        }
+    }
+
+    files
+}
+
+/// Parse deb822 `.sources` stanzas into [`SourceEntry`] values, one per
+/// `Types` × `URIs` combination so host-based matching works uniformly with
+/// the one-line format. Stanzas are separated by blank lines.
+fn parse_deb822(content: &str) -> Vec<SourceEntry> {
+    let mut entries = Vec::new();
+
+    // Group lines into stanzas separated by blank lines, tolerating whitespace-
+    // only separators and CRLF endings.
+    let mut stanza: Vec<&str> = Vec::new();
+    let mut stanzas: Vec<Vec<&str>> = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            if !stanza.is_empty() {
+                stanzas.push(std::mem::take(&mut stanza));
+            }
+        } else {
+            stanza.push(line);
+        }
+    }
+    if !stanza.is_empty() {
+        stanzas.push(stanza);
+    }
+
+    for stanza in stanzas {
+        let mut types: Vec<&str> = Vec::new();
+        let mut uris: Vec<&str> = Vec::new();
+        let mut suites: Vec<&str> = Vec::new();
+        let mut enabled = true;
+
+        for line in stanza {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "types" => types = value.split_whitespace().collect(),
+                "uris" => uris = value.split_whitespace().collect(),
+                "suites" => suites = value.split_whitespace().collect(),
+                // `Enabled: no` disables the stanza; anything else keeps the default.
+                "enabled" => enabled = !value.eq_ignore_ascii_case("no"),
+                _ => {}
+            }
+        }
+
+        if types.is_empty() || uris.is_empty() {
+            continue;
+        }
+        let suite = suites.join(" ");
+        for ty in &types {
+            let is_src = *ty == "deb-src";
+            for uri in &uris {
+                entries.push(SourceEntry {
+                    enabled,
+                    is_src,
+                    uri: uri.to_string(),
+                    suite: suite.clone(),
+                });
+            }
+        }
+    }
+
+    entries
+}
+
+/// Find an enabled, non-`deb-src` MooseFS entry for the given `codename`,
+/// returning the source file it lives in. Matches on host + path (the repo base
+/// URI) rather than the exact line so an extra component does not cause a
+/// duplicate, and on the suite so a repo for a different release is not mistaken
+/// for the current one. Passing `None` for `codename` matches any suite.
+fn find_moosefs_repo<'a>(files: &'a [SourceFile], codename: Option<&str>) -> Option<&'a Path> {
+    files.iter().find_map(|file| {
+        let present = file.entries.iter().any(|entry| {
+            entry.enabled
+                && !entry.is_src
+                && entry.uri.starts_with(MOOSEFS_REPO_BASE)
+                && codename.map_or(true, |c| entry.suite.split_whitespace().any(|s| s == c))
+        });
+        present.then(|| file.path.as_path())
+    })
+}
+
+/// Read the Debian base codename from `/etc/os-release`.
+fn debian_codename() -> Option<String> {
+    let content = fs::read_to_string("/etc/os-release").ok()?;
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix("VERSION_CODENAME=")?;
+        Some(value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Ensure the MooseFS APT repository is configured, writing back idempotently:
+/// re-running against an already-configured system produces no diff.
+fn ensure_moosefs_repo() -> Result<()> {
+    let result = configure_moosefs_repo()?;
+    println!("{} {}", result.glyph(), result.message);
+    if result.level == Level::Fail {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Append the MooseFS repo line for the running release if it is not already
+/// present. Returns a [`CheckResult`] describing what happened.
+fn configure_moosefs_repo() -> Result<CheckResult> {
+    let codename = match debian_codename() {
+        Some(codename) if KNOWN_CODENAMES.contains(&codename.as_str()) => codename,
+        Some(codename) => {
+            return Ok(CheckResult::warn(format!(
+                "unknown Debian codename '{}'; refusing to add MooseFS repo (known: {})",
+                codename,
+                KNOWN_CODENAMES.join(", ")
+            )));
+        }
+        None => {
+            return Ok(CheckResult::warn(
+                "could not determine Debian codename from /etc/os-release".to_string(),
+            ));
+        }
+    };
+
+    let files = parse_sources_list_d(Path::new(SOURCES_LIST_D));
+    if let Some(path) = find_moosefs_repo(&files, Some(&codename)) {
+        return Ok(CheckResult::pass(format!(
+            "MooseFS APT repository already configured in {}",
+            path.display()
+        )));
+    }
+
+    let line = format!("deb {}/{} {} main\n", MOOSEFS_REPO_BASE, codename, codename);
+    let target = Path::new(SOURCES_LIST_D).join("moosefs.list");
+    let mut content = match fs::read_to_string(&target) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", target.display()));
+        }
+    };
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&line);
+    fs::write(&target, content)
+        .with_context(|| format!("Failed to write {}", target.display()))?;
+
+    Ok(CheckResult::pass(format!(
+        "added MooseFS APT repository for {} to {}",
+        codename,
+        target.display()
+    )))
+}
+
+/// Severity of a single preflight check.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Outcome of one independent environment check.
+struct CheckResult {
+    level: Level,
+    message: String,
+}
+
+impl CheckResult {
+    fn pass(message: impl Into<String>) -> Self {
+        CheckResult { level: Level::Pass, message: message.into() }
+    }
+
+    fn warn(message: impl Into<String>) -> Self {
+        CheckResult { level: Level::Warn, message: message.into() }
+    }
+
+    fn fail(message: impl Into<String>) -> Self {
+        CheckResult { level: Level::Fail, message: message.into() }
+    }
+
+    fn glyph(&self) -> char {
+        match self.level {
+            Level::Pass => '✓',
+            Level::Warn => '⚠',
+            Level::Fail => '✗',
+        }
+    }
+}
+
+/// Validate the whole MooseFS-on-PVE environment and print a pass/warn/fail
+/// summary. Exits non-zero if any check fails so it can gate an installer.
+fn doctor() -> Result<()> {
+    println!("MooseFS Preflight Check for Proxmox VE");
     println!("========================================\n");
 
-    // Get the currently installed pvemanagerlib.js
-    let current_js = Path::new("/usr/share/pve-manager/js/pvemanagerlib.js");
+    let checks = vec![
+        check_pve_manager(),
+        check_not_already_patched(),
+        check_moosefs_client(),
+        check_storage_cfg(),
+        check_apt_repo(),
+    ];
 
-    if !current_js.exists() {
-        anyhow::bail!("pvemanagerlib.js not found. Is pve-manager installed?");
+    let mut failed = false;
+    for check in &checks {
+        println!("{} {}", check.glyph(), check.message);
+        if check.level == Level::Fail {
+            failed = true;
+        }
     }
 
-    println!("✓ Found installed pvemanagerlib.js");
+    println!();
+    if failed {
+        println!("✗ One or more checks failed.");
+        std::process::exit(1);
+    }
+    println!("✓ Environment looks ready for MooseFS.");
+    Ok(())
+}
 
-    // Get pve-manager version
-    let version_output = Command::new("dpkg-query")
+/// (1) `pve-manager` is installed and its version is supported.
+fn check_pve_manager() -> CheckResult {
+    match pve_manager_version() {
+        Ok(version) => {
+            let version = version.trim();
+            if SUPPORTED_PVE_VERSIONS.iter().any(|v| version.starts_with(v)) {
+                CheckResult::pass(format!("pve-manager {} is installed and supported", version))
+            } else {
+                CheckResult::warn(format!(
+                    "pve-manager {} is installed but untested (supported: {})",
+                    version,
+                    SUPPORTED_PVE_VERSIONS.join(", ")
+                ))
+            }
+        }
+        Err(_) => CheckResult::fail("pve-manager is not installed".to_string()),
+    }
+}
+
+/// (2) the MooseFS storage definition is not already present in the installed JS.
+fn check_not_already_patched() -> CheckResult {
+    match fs::read_to_string(INSTALLED_JS) {
+        Ok(content) => {
+            if content.contains(MOOSEFS_MARKER) {
+                CheckResult::warn(format!("{} already contains the MooseFS patch", INSTALLED_JS))
+            } else {
+                CheckResult::pass("pvemanagerlib.js is unpatched".to_string())
+            }
+        }
+        Err(_) => CheckResult::fail(format!("{} not found; is pve-manager installed?", INSTALLED_JS)),
+    }
+}
+
+/// (3) the MooseFS client package is installed and its binaries are on PATH.
+fn check_moosefs_client() -> CheckResult {
+    let package_installed = Command::new("dpkg-query")
+        .args(&["-W", "-f=${Status}", "moosefs-client"])
+        .output()
+        .map_or(false, |o| {
+            o.status.success() && String::from_utf8_lossy(&o.stdout).contains("install ok installed")
+        });
+
+    let missing_binaries: Vec<&str> = ["mfsmount", "mfsbdev"]
+        .iter()
+        .copied()
+        .filter(|bin| !binary_on_path(bin))
+        .collect();
+
+    if !package_installed {
+        CheckResult::fail("moosefs-client package is not installed".to_string())
+    } else if !missing_binaries.is_empty() {
+        CheckResult::fail(format!("missing binaries on PATH: {}", missing_binaries.join(", ")))
+    } else {
+        CheckResult::pass("moosefs-client is installed with mfsmount/mfsbdev on PATH".to_string())
+    }
+}
+
+/// (4) `/etc/pve/storage.cfg` does not declare a `moosefs:` entry with a broken
+/// mount point.
+fn check_storage_cfg() -> CheckResult {
+    let path = "/etc/pve/storage.cfg";
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        // A missing storage.cfg is not an error for a fresh install.
+        Err(_) => return CheckResult::pass("no MooseFS storage configured yet".to_string()),
+    };
+
+    let mut in_moosefs = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("moosefs:") {
+            in_moosefs = true;
+            continue;
+        }
+        if in_moosefs {
+            if !line.starts_with(char::is_whitespace) {
+                in_moosefs = false;
+                continue;
+            }
+            if let Some(mountpoint) = trimmed.strip_prefix("path ") {
+                let mountpoint = mountpoint.trim();
+                if !Path::new(mountpoint).is_dir() {
+                    return CheckResult::fail(format!(
+                        "moosefs storage mount point {} does not exist",
+                        mountpoint
+                    ));
+                }
+            }
+        }
+    }
+
+    CheckResult::pass("storage.cfg has no broken MooseFS mount point".to_string())
+}
+
+/// (5) the MooseFS APT repository is configured.
+fn check_apt_repo() -> CheckResult {
+    let files = parse_sources_list_d(Path::new(SOURCES_LIST_D));
+    let codename = debian_codename();
+    match find_moosefs_repo(&files, codename.as_deref()) {
+        Some(path) => CheckResult::pass(format!("MooseFS APT repository is configured in {}", path.display())),
+        None => CheckResult::warn("MooseFS APT repository is not configured".to_string()),
+    }
+}
+
+/// Query the installed pve-manager version via dpkg.
+fn pve_manager_version() -> Result<String> {
+    let output = Command::new("dpkg-query")
         .args(&["-W", "-f=${Version}", "pve-manager"])
         .output()
-        .context("Failed to get pve-manager version")?;
+        .context("Failed to query pve-manager version")?;
 
-    let version = String::from_utf8_lossy(&version_output.stdout);
-    println!("✓ pve-manager version: {}", version);
+    if !output.status.success() {
+        anyhow::bail!("pve-manager is not installed");
+    }
 
-    // Create temp directory
-    let temp_dir = TempDir::new().context("Failed to create temp directory")?;
-    let temp_path = temp_dir.path();
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
 
-    // Download clean pve-manager deb
+/// Download a clean `pve-manager` package and extract its pvemanagerlib.js,
+/// returning the path to the extracted file inside `temp_path`.
+fn download_clean_js(temp_path: &Path) -> Result<PathBuf> {
     println!("\nDownloading clean pve-manager package...");
 
     let download_result = Command::new("apt-get")
@@ -184,13 +632,56 @@ fn main() -> Result<()> {
 
     println!("✓ Extracted pvemanagerlib.js");
 
+    Ok(clean_js)
+}
+
+/// Whether `binary` resolves on the current PATH.
+fn binary_on_path(binary: &str) -> bool {
+    Command::new("sh")
+        .args(&["-c", &format!("command -v {}", binary)])
+        .output()
+        .map_or(false, |o| o.status.success())
+}
+
+fn generate() -> Result<()> {
+    println!("MooseFS Patch Generator for Proxmox VE");
+    println!("========================================\n");
+
+    // Get the currently installed pvemanagerlib.js
+    let current_js = Path::new(INSTALLED_JS);
+
+    if !current_js.exists() {
+        anyhow::bail!("pvemanagerlib.js not found. Is pve-manager installed?");
+    }
+
+    println!("✓ Found installed pvemanagerlib.js");
+
+    // Get pve-manager version
+    let version_output = Command::new("dpkg-query")
+        .args(&["-W", "-f=${Version}", "pve-manager"])
+        .output()
+        .context("Failed to get pve-manager version")?;
+
+    let version = String::from_utf8_lossy(&version_output.stdout);
+    println!("✓ pve-manager version: {}", version);
+
+    // Resolve the textual anchors for this release before touching anything.
+    let anchors = resolve_anchors(&version)?;
+
+    // Create temp directory
+    let temp_dir = TempDir::new().context("Failed to create temp directory")?;
+    let temp_path = temp_dir.path();
+
+    // Download and extract a clean pve-manager to diff against
+    let clean_js = download_clean_js(temp_path)?;
+
     // Read clean file
     let clean_content = fs::read_to_string(&clean_js)
         .context("Failed to read clean pvemanagerlib.js")?;
 
     // Generate modified version
     println!("\nGenerating patched version...");
-    let modified_content = apply_moosefs_changes(&clean_content)?;
+    let modified_content = apply_moosefs_changes(&clean_content, anchors)?;
 
     // Write modified version
     let modified_js = temp_path.join("pvemanagerlib.patched.js");
@@ -246,7 +737,52 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn apply_moosefs_changes(content: &str) -> Result<String> {
+/// Resolve the anchor set for the installed pve-manager `version`, aborting
+/// with a clear message if it falls outside every known-good range.
+fn resolve_anchors(version: &str) -> Result<&'static AnchorSet> {
+    let (major, minor) = parse_version(version)
+        .with_context(|| format!("Could not parse pve-manager version '{}'", version))?;
+
+    for entry in ANCHOR_TABLE {
+        if (major, minor) >= entry.min && (major, minor) <= entry.max {
+            return Ok(&entry.anchors);
+        }
+    }
+
+    let last_known = ANCHOR_TABLE
+        .last()
+        .map(|e| format!("{}.{}", e.max.0, e.max.1))
+        .unwrap_or_else(|| "none".to_string());
+    anyhow::bail!(
+        "pve-manager {} is outside the known-good range (last known-good: {}); \
+         refusing to produce a silently empty patch",
+        version,
+        last_known
+    );
+}
+
+/// Parse the leading `major.minor` of a dpkg version string.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    // The minor may carry a trailing suffix such as "4+deb12u1".
+    let minor_part = parts.next()?;
+    let minor = minor_part
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((major, minor))
+}
+
+fn apply_moosefs_changes(content: &str, anchors: &AnchorSet) -> Result<String> {
+    // Idempotency: a file that already carries the MooseFS panel must not be
+    // patched again, otherwise the blocks are duplicated and the resulting
+    // Ext.define is broken. Return it unchanged so the generated patch is empty.
+    if content.contains(MOOSEFS_MARKER) {
+        return Ok(content.to_string());
+    }
+
     let lines: Vec<&str> = content.lines().collect();
     let mut result = Vec::new();
     let mut i = 0;
@@ -254,16 +790,15 @@ fn apply_moosefs_changes(content: &str) -> Result<String> {
     while i < lines.len() {
         let line = lines[i];
 
-        // Insert storage type after the last storage before 'cephfs'
-        if line.trim().starts_with("cephfs: {") {
-            // Add MooseFS storage type before CephFS
+        // Insert storage type before the resolved anchor storage type
+        if line.trim().starts_with(anchors.storage_type_anchor) {
             for moosefs_line in STORAGE_TYPES_ADDITION.lines() {
                 result.push(moosefs_line.to_string());
             }
         }
 
-        // Insert UI panel definition before BTRFSInputPanel
-        if line.contains("Ext.define('PVE.storage.BTRFSInputPanel'") {
+        // Insert UI panel definition before the resolved anchor panel
+        if line.contains(anchors.panel_anchor) {
             // Add MooseFS UI panel before BTRFS
             for panel_line in MOOSEFS_UI_PANEL.lines() {
                 result.push(panel_line.to_string());
@@ -277,3 +812,422 @@ fn apply_moosefs_changes(content: &str) -> Result<String> {
 
     Ok(result.join("\n"))
 }
+
+/// Reverse of [`apply_moosefs_changes`]: strip the previously inserted MooseFS
+/// blocks, returning the content unchanged if no marker is found.
+///
+/// Removal anchors on the stable markers (`PVE.storage.MooseFSController` /
+/// `moosefs: {`) and follows brace/paren nesting to the closing delimiter
+/// rather than matching the inserted text verbatim, so a re-indented or
+/// reflowed block is still removed. If the marker is present but a block's
+/// boundaries cannot be located, this fails loudly rather than emitting a
+/// partial diff.
+fn remove_moosefs_changes(content: &str) -> Result<String> {
+    if !content.contains(MOOSEFS_MARKER) {
+        return Ok(content.to_string());
+    }
+
+    let mut lines: Vec<&str> = content.lines().collect();
+
+    // Drop the panel definition (and the blank line we appended after it). The
+    // block spans two `Ext.define` statements, ending when the InputPanel one
+    // closes.
+    let panel_start = lines
+        .iter()
+        .position(|l| l.contains("Ext.define('PVE.storage.MooseFSController'"))
+        .context("MooseFS marker present but the panel definition could not be located")?;
+    let panel_end = balanced_end(&lines, panel_start, '(', ')', Some(MOOSEFS_MARKER))
+        .context("MooseFS panel definition has no closing delimiter")?;
+    let drain_end = if lines.get(panel_end + 1).map_or(false, |l| l.is_empty()) {
+        panel_end + 2
+    } else {
+        panel_end + 1
+    };
+    lines.drain(panel_start..drain_end);
+
+    // Drop the storage type entry, following `{`/`}` nesting to its close.
+    let storage_start = lines
+        .iter()
+        .position(|l| l.trim().starts_with("moosefs: {"))
+        .context("MooseFS marker present but the storage type entry could not be located")?;
+    let storage_end = balanced_end(&lines, storage_start, '{', '}', None)
+        .context("MooseFS storage type entry has no closing delimiter")?;
+    lines.drain(storage_start..=storage_end);
+
+    Ok(lines.join("\n"))
+}
+
+/// Index of the line that closes the block starting at `start`, matched by
+/// `open`/`close` delimiter nesting. When `require_marker` is set, the close is
+/// only accepted once a line containing that marker has been seen (used to skip
+/// past the first of two `Ext.define` statements).
+fn balanced_end(
+    lines: &[&str],
+    start: usize,
+    open: char,
+    close: char,
+    require_marker: Option<&str>,
+) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut started = false;
+    let mut marker_seen = require_marker.is_none();
+    for (offset, line) in lines[start..].iter().enumerate() {
+        if let Some(marker) = require_marker {
+            if line.contains(marker) {
+                marker_seen = true;
+            }
+        }
+        for c in line.chars() {
+            if c == open {
+                depth += 1;
+                started = true;
+            } else if c == close {
+                depth -= 1;
+            }
+        }
+        if started && depth == 0 && marker_seen {
+            return Some(start + offset);
+        }
+    }
+    None
+}
+
+/// Strip the MooseFS blocks from a copy of the installed pvemanagerlib.js and
+/// write the reverse unified diff to `pve-moosefs-uninstall.patch` so operators
+/// can revert before a pve-manager upgrade. The installed file is not modified.
+fn uninstall() -> Result<()> {
+    println!("MooseFS Patch Remover for Proxmox VE");
+    println!("========================================\n");
+
+    let installed_js = Path::new(INSTALLED_JS);
+    if !installed_js.exists() {
+        anyhow::bail!("pvemanagerlib.js not found. Is pve-manager installed?");
+    }
+
+    let current_content = fs::read_to_string(installed_js)
+        .context("Failed to read installed pvemanagerlib.js")?;
+
+    if !current_content.contains(MOOSEFS_MARKER) {
+        println!("✓ pvemanagerlib.js is not patched; nothing to do");
+        return Ok(());
+    }
+
+    let reverted_content = remove_moosefs_changes(&current_content)?;
+
+    let temp_dir = TempDir::new().context("Failed to create temp directory")?;
+    let reverted_js = temp_dir.path().join("pvemanagerlib.reverted.js");
+    fs::write(&reverted_js, &reverted_content)
+        .context("Failed to write reverted pvemanagerlib.js")?;
+
+    println!("Generating reverse patch...");
+    let diff_output = Command::new("diff")
+        .args(&[
+            "-u",
+            "--label", "pvemanagerlib.js",
+            "--label", "pvemanagerlib.reverted.js",
+            installed_js.to_str().unwrap(),
+            reverted_js.to_str().unwrap(),
+        ])
+        .output()
+        .context("Failed to generate diff")?;
+
+    let patch_content = String::from_utf8(diff_output.stdout)
+        .context("Diff output is not valid UTF-8")?;
+
+    let patch_path = PathBuf::from("pve-moosefs-uninstall.patch");
+    fs::write(&patch_path, patch_content)
+        .context("Failed to write uninstall patch file")?;
+
+    println!("✓ Generated pve-moosefs-uninstall.patch");
+    println!("\n✅ Uninstall patch generation complete");
+
+    Ok(())
+}
+
+/// A structured environment report, suitable for attaching to a bug report.
+struct EnvInfo {
+    pve_manager: Option<String>,
+    moosefs_client: Option<String>,
+    moosefs_chunkserver: Option<String>,
+    kernel: Option<String>,
+    patched: bool,
+    patch_line: Option<usize>,
+    patch_byte: Option<usize>,
+    installed_sha256: Option<String>,
+    clean_sha256: Option<String>,
+    storages: Vec<String>,
+}
+
+/// Gather and print the MooseFS/PVE environment report.
+fn info(as_json: bool) -> Result<()> {
+    let installed_content = fs::read_to_string(INSTALLED_JS).ok();
+
+    let (patched, patch_byte, patch_line) = match &installed_content {
+        Some(content) => match content.find(MOOSEFS_MARKER) {
+            Some(byte) => {
+                let line = content[..byte].matches('\n').count() + 1;
+                (true, Some(byte), Some(line))
+            }
+            None => (false, None, None),
+        },
+        None => (false, None, None),
+    };
+
+    let installed_sha256 = installed_content.as_deref().map(sha256_hex);
+
+    // Fetching a clean copy requires a download, so only attempt it when the
+    // installed file is present.
+    let clean_sha256 = if installed_content.is_some() {
+        clean_js_sha256().ok()
+    } else {
+        None
+    };
+
+    let report = EnvInfo {
+        pve_manager: pve_manager_version().ok().map(|v| v.trim().to_string()),
+        moosefs_client: package_version("moosefs-client"),
+        moosefs_chunkserver: package_version("moosefs-chunkserver"),
+        kernel: kernel_version(),
+        patched,
+        patch_line,
+        patch_byte,
+        installed_sha256,
+        clean_sha256,
+        storages: configured_moosefs_storages(),
+    };
+
+    if as_json {
+        print!("{}", report.to_json());
+    } else {
+        report.print_human();
+    }
+
+    Ok(())
+}
+
+impl EnvInfo {
+    fn print_human(&self) {
+        println!("MooseFS / PVE Environment Report");
+        println!("========================================\n");
+        println!("pve-manager:         {}", opt(&self.pve_manager));
+        println!("moosefs-client:      {}", opt(&self.moosefs_client));
+        println!("moosefs-chunkserver: {}", opt(&self.moosefs_chunkserver));
+        println!("kernel:              {}", opt(&self.kernel));
+        match (self.patched, self.patch_line, self.patch_byte) {
+            (true, Some(line), Some(byte)) => {
+                println!("pvemanagerlib.js:    patched (line {}, byte {})", line, byte);
+            }
+            _ => println!("pvemanagerlib.js:    not patched"),
+        }
+        println!("installed sha256:    {}", opt(&self.installed_sha256));
+        println!("clean sha256:        {}", opt(&self.clean_sha256));
+        if self.storages.is_empty() {
+            println!("moosefs storages:    (none)");
+        } else {
+            println!("moosefs storages:    {}", self.storages.join(", "));
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let storages = self
+            .storages
+            .iter()
+            .map(|s| format!("\"{}\"", json_escape(s)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{{\n  \"pve_manager\": {},\n  \"moosefs_client\": {},\n  \"moosefs_chunkserver\": {},\n  \"kernel\": {},\n  \"patched\": {},\n  \"patch_line\": {},\n  \"patch_byte\": {},\n  \"installed_sha256\": {},\n  \"clean_sha256\": {},\n  \"storages\": [{}]\n}}\n",
+            json_opt_str(&self.pve_manager),
+            json_opt_str(&self.moosefs_client),
+            json_opt_str(&self.moosefs_chunkserver),
+            json_opt_str(&self.kernel),
+            self.patched,
+            json_opt_num(self.patch_line),
+            json_opt_num(self.patch_byte),
+            json_opt_str(&self.installed_sha256),
+            json_opt_str(&self.clean_sha256),
+            storages,
+        )
+    }
+}
+
+fn opt(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("(unknown)")
+}
+
+fn json_opt_str(value: &Option<String>) -> String {
+    match value {
+        Some(s) => format!("\"{}\"", json_escape(s)),
+        None => "null".to_string(),
+    }
+}
+
+fn json_opt_num(value: Option<usize>) -> String {
+    value.map_or_else(|| "null".to_string(), |n| n.to_string())
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Query the installed version of a package, returning `None` if absent.
+fn package_version(package: &str) -> Option<String> {
+    let output = Command::new("dpkg-query")
+        .args(&["-W", "-f=${Version}", package])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// The running kernel release, via `uname -r`.
+fn kernel_version() -> Option<String> {
+    let output = Command::new("uname").arg("-r").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The SHA-256 of `content`, computed by shelling out to `sha256sum`.
+fn sha256_hex(content: &str) -> String {
+    use std::io::Write;
+    let mut child = match Command::new("sha256sum")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => return "(unknown)".to_string(),
+    };
+    if let Some(stdin) = child.stdin.as_mut() {
+        let _ = stdin.write_all(content.as_bytes());
+    }
+    match child.wait_with_output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .unwrap_or("(unknown)")
+            .to_string(),
+        Err(_) => "(unknown)".to_string(),
+    }
+}
+
+/// SHA-256 of a freshly downloaded clean pvemanagerlib.js.
+fn clean_js_sha256() -> Result<String> {
+    let temp_dir = TempDir::new().context("Failed to create temp directory")?;
+    let clean_js = download_clean_js(temp_dir.path())?;
+    let content = fs::read_to_string(&clean_js).context("Failed to read clean pvemanagerlib.js")?;
+    Ok(sha256_hex(&content))
+}
+
+/// The ids of every `moosefs:` storage declared in `/etc/pve/storage.cfg`.
+fn configured_moosefs_storages() -> Vec<String> {
+    let content = match fs::read_to_string("/etc/pve/storage.cfg") {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("moosefs:"))
+        .map(|id| id.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A trimmed-down snippet modeled on pvemanagerlib.js, carrying the two
+    // anchors the generator inserts around.
+    const SNIPPET: &str = "\
+Ext.define('PVE.storage.StorageView', {
+    storageSchema: {
+        dir: {
+            name: 'Directory',
+        },
+        cephfs: {
+            name: 'CephFS',
+        },
+    },
+});
+Ext.define('PVE.storage.BTRFSInputPanel', {
+    extend: 'PVE.panel.StorageBase',
+});";
+
+    fn anchors_8x() -> &'static AnchorSet {
+        resolve_anchors("8.2.4").expect("8.2.4 resolves")
+    }
+
+    #[test]
+    fn resolve_anchors_in_range() {
+        let anchors = resolve_anchors("8.0.3").unwrap();
+        assert_eq!(anchors.storage_type_anchor, "cephfs: {");
+        assert_eq!(anchors.panel_anchor, "Ext.define('PVE.storage.BTRFSInputPanel'");
+    }
+
+    #[test]
+    fn resolve_anchors_out_of_range_bails() {
+        assert!(resolve_anchors("9.0.0").is_err());
+        assert!(resolve_anchors("7.4").is_err());
+    }
+
+    #[test]
+    fn apply_inserts_both_blocks() {
+        let patched = apply_moosefs_changes(SNIPPET, anchors_8x()).unwrap();
+        assert!(patched.contains(MOOSEFS_MARKER));
+        assert!(patched.contains("moosefs: {"));
+    }
+
+    #[test]
+    fn apply_is_idempotent() {
+        let once = apply_moosefs_changes(SNIPPET, anchors_8x()).unwrap();
+        let twice = apply_moosefs_changes(&once, anchors_8x()).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn apply_then_remove_round_trips() {
+        let patched = apply_moosefs_changes(SNIPPET, anchors_8x()).unwrap();
+        let reverted = remove_moosefs_changes(&patched).unwrap();
+        assert_eq!(reverted, SNIPPET);
+    }
+
+    #[test]
+    fn remove_on_unpatched_is_noop() {
+        assert_eq!(remove_moosefs_changes(SNIPPET).unwrap(), SNIPPET);
+    }
+
+    #[test]
+    fn parse_deb822_detects_moosefs_source() {
+        let content = format!(
+            "Types: deb\nURIs: {}/bookworm\nSuites: bookworm\nComponents: main\nEnabled: yes\n",
+            MOOSEFS_REPO_BASE
+        );
+        let files = vec![SourceFile {
+            path: PathBuf::from("/etc/apt/sources.list.d/moosefs.sources"),
+            entries: parse_deb822(&content),
+        }];
+        assert!(find_moosefs_repo(&files, Some("bookworm")).is_some());
+        // A repo for a different release must not be mistaken for this one.
+        assert!(find_moosefs_repo(&files, Some("trixie")).is_none());
+    }
+}